@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use crate::{ColorScheme, EffectsDirty, NoiseGen};
+
+const FFT_SIZE: usize = 1024;
+const RING_BUFFER_CAPACITY: usize = FFT_SIZE * 4;
+/// Exponential smoothing factor applied to each band per update, so the motion pulses
+/// with the music instead of jittering sample-to-sample.
+const SMOOTHING: f32 = 0.2;
+
+const LOW_BAND_HZ: (f32, f32) = (20.0, 250.0);
+const HIGH_BAND_HZ: (f32, f32) = (2000.0, 8000.0);
+
+/// Per-band energies derived from the default input device, updated by [`update_band_energies`].
+#[derive(Resource, Default)]
+pub struct BandEnergies {
+    pub low: f32,
+    pub mid: f32,
+    pub high: f32,
+    pub loudness: f32,
+}
+
+/// Toggle and gain for audio-reactive modulation, flipped by `keyboard_input`'s `V` shortcut.
+#[derive(Resource)]
+pub struct AudioReactivity {
+    pub enabled: bool,
+    pub gain: f32,
+    /// Luminosity captured at the moment reactivity was enabled; loudness modulates around it.
+    pub base_luminosity: f32,
+}
+
+impl Default for AudioReactivity {
+    fn default() -> Self {
+        AudioReactivity {
+            enabled: false,
+            gain: 1.0,
+            base_luminosity: 0.0,
+        }
+    }
+}
+
+/// Owns the cpal input stream. Not `Send`/`Sync` on all platforms, so it lives as a
+/// non-send resource; captured samples flow out through a shared ring buffer instead.
+pub struct AudioCapture {
+    _stream: Option<cpal::Stream>,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: f32,
+}
+
+impl AudioCapture {
+    fn new() -> Self {
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(RING_BUFFER_CAPACITY)));
+
+        match Self::build_stream(samples.clone()) {
+            Ok((stream, sample_rate)) => AudioCapture {
+                _stream: Some(stream),
+                samples,
+                sample_rate,
+            },
+            Err(err) => {
+                warn!("audio capture unavailable, audio reactivity will stay silent: {err}");
+                AudioCapture {
+                    _stream: None,
+                    samples,
+                    sample_rate: 48_000.0,
+                }
+            }
+        }
+    }
+
+    fn build_stream(
+        samples: Arc<Mutex<VecDeque<f32>>>,
+    ) -> Result<(cpal::Stream, f32), Box<dyn std::error::Error>> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or("no default audio input device")?;
+        let config = device.default_input_config()?;
+        let sample_rate = config.sample_rate().0 as f32;
+        let channels = config.channels() as usize;
+
+        let stream = device.build_input_stream(
+            &config.into(),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let mut buf = samples.lock().unwrap();
+                for frame in data.chunks(channels.max(1)) {
+                    let mono = frame.iter().sum::<f32>() / frame.len() as f32;
+                    buf.push_back(mono);
+                }
+                while buf.len() > RING_BUFFER_CAPACITY {
+                    buf.pop_front();
+                }
+            },
+            |err| warn!("audio input stream error: {err}"),
+            None,
+        )?;
+        stream.play()?;
+
+        Ok((stream, sample_rate))
+    }
+
+    fn take_latest(&self, n: usize) -> Vec<f32> {
+        let buf = self.samples.lock().unwrap();
+        if buf.len() < n {
+            return Vec::new();
+        }
+        buf.iter().rev().take(n).rev().copied().collect()
+    }
+}
+
+pub fn setup_audio_capture(world: &mut World) {
+    world.insert_non_send_resource(AudioCapture::new());
+}
+
+/// Runs an FFT over the latest captured samples and smooths the result into [`BandEnergies`],
+/// feeding [`ColorScheme::luminosity`] directly; degrades gracefully (decaying toward silence)
+/// when no input device is present or too few samples have arrived. Does nothing while
+/// reactivity is off.
+pub fn update_band_energies(
+    capture: NonSend<AudioCapture>,
+    reactivity: Res<AudioReactivity>,
+    mut bands: ResMut<BandEnergies>,
+    mut color_scheme: ResMut<ColorScheme>,
+    mut effects_dirty: ResMut<EffectsDirty>,
+) {
+    if !reactivity.enabled {
+        return;
+    }
+
+    let samples = capture.take_latest(FFT_SIZE);
+    if samples.len() < FFT_SIZE {
+        bands.low *= 1. - SMOOTHING;
+        bands.mid *= 1. - SMOOTHING;
+        bands.high *= 1. - SMOOTHING;
+        bands.loudness *= 1. - SMOOTHING;
+        return;
+    }
+
+    let spectrum = fft_magnitudes(&samples);
+    let (low, mid, high) = bucket_bands(&spectrum, capture.sample_rate);
+    let loudness = samples.iter().map(|s| s.abs()).sum::<f32>() / samples.len() as f32;
+
+    bands.low += (low - bands.low) * SMOOTHING;
+    bands.mid += (mid - bands.mid) * SMOOTHING;
+    bands.high += (high - bands.high) * SMOOTHING;
+    bands.loudness += (loudness - bands.loudness) * SMOOTHING;
+
+    color_scheme.luminosity = reactivity.base_luminosity * (1. + bands.loudness * reactivity.gain);
+    effects_dirty.0 = true;
+}
+
+/// Applies the smoothed high-band energy on top of the user-set noise scale; runs before
+/// `move_particles` so the flow field itself reacts to the music.
+pub fn apply_noise_modulation(
+    reactivity: Res<AudioReactivity>,
+    bands: Res<BandEnergies>,
+    mut noise_gen: ResMut<NoiseGen>,
+) {
+    noise_gen.noise_scale = if reactivity.enabled {
+        noise_gen.base_noise_scale * (1. + bands.high * reactivity.gain)
+    } else {
+        noise_gen.base_noise_scale
+    };
+}
+
+fn fft_magnitudes(samples: &[f32]) -> Vec<f32> {
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(samples.len());
+
+    let mut buffer: Vec<Complex<f32>> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            // Hann window to reduce spectral leakage
+            let w = 0.5
+                - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (samples.len() - 1) as f32).cos();
+            Complex::new(s * w, 0.0)
+        })
+        .collect();
+
+    fft.process(&mut buffer);
+
+    buffer[..buffer.len() / 2].iter().map(|c| c.norm()).collect()
+}
+
+fn bucket_bands(spectrum: &[f32], sample_rate: f32) -> (f32, f32, f32) {
+    let bin_hz = sample_rate / (spectrum.len() * 2) as f32;
+
+    let bucket = |lo_hz: f32, hi_hz: f32| -> f32 {
+        let lo_bin = (lo_hz / bin_hz) as usize;
+        let hi_bin = ((hi_hz / bin_hz) as usize).min(spectrum.len());
+        if hi_bin <= lo_bin {
+            return 0.0;
+        }
+        spectrum[lo_bin..hi_bin].iter().sum::<f32>() / (hi_bin - lo_bin) as f32
+    };
+
+    let low = bucket(LOW_BAND_HZ.0, LOW_BAND_HZ.1);
+    let high = bucket(HIGH_BAND_HZ.0, HIGH_BAND_HZ.1);
+    let mid = bucket(LOW_BAND_HZ.1, HIGH_BAND_HZ.0);
+
+    (low, mid, high)
+}
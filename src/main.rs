@@ -2,14 +2,33 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use bevy::{prelude::*, window::{WindowResized, WindowResolution}, core_pipeline::{tonemapping::Tonemapping, bloom::BloomSettings}, render::color};
+#[cfg(feature = "hanabi-trails")]
 use bevy_hanabi::prelude::*;
-use libnoise::prelude::*;
+#[cfg(not(feature = "hanabi-trails"))]
+use bevy::sprite::Material2dPlugin;
+use bevy_egui::EguiPlugin;
+
+mod audio;
+mod noise;
+mod particle_kind;
+mod trail;
+#[cfg(feature = "hanabi-trails")]
+mod trail_hanabi;
+#[cfg(not(feature = "hanabi-trails"))]
+mod trail_feedback;
+mod ui;
+
+use audio::{AudioReactivity, BandEnergies};
+use noise::NoiseGen;
+use particle_kind::{ParticleKind, ParticleKindWeights};
+use trail::ActiveTrailRenderer;
+#[cfg(not(feature = "hanabi-trails"))]
+use trail_feedback::FadeMaterial;
 
 const WIDTH: usize = 1200;
 const HEIGHT: usize = 800;
 
 const NUM_PARTICLES: f32 = 2000.;
-const PARTICLE_RADIUS: f32 = 2.;
 
 const SPEED_FACTOR: f32 = 1.;
 
@@ -27,37 +46,85 @@ fn main() {
             }),
             ..Default::default()
         }))
-        .add_plugins(HanabiPlugin)
+        .add_plugins(TrailRendererPlugin)
+        .add_plugins(EguiPlugin)
         .insert_resource(WindowSize {
             width: WIDTH as f32,
             height: HEIGHT as f32
         })
-        .insert_resource(NoiseGen { gen: Source::perlin(seed), seed, noise_scale: 0.001 })
+        .insert_resource(NoiseGen::new(seed, 0.001))
         .insert_resource(ParticleCount(NUM_PARTICLES as usize))
         .insert_resource(ColorScheme {
             background: Color::BLACK,
             particle: Color::WHITE,
             luminosity: LUMINOSITY
         })
+        .insert_resource(SpeedFactor(SPEED_FACTOR))
+        .insert_resource(EffectsDirty(false))
+        .init_resource::<ActiveTrailRenderer>()
+        .init_resource::<AudioReactivity>()
+        .init_resource::<BandEnergies>()
+        .init_resource::<ParticleKindWeights>()
         // .insert_resource(Luminosity(LUMINOSITY))
-        .add_systems(Startup, (setup_camera, add_particles))
-        .add_systems(PostStartup, draw_trails)
-        .add_systems(Update, (move_particles, keyboard_input, ensure_particle_count)) // check_field, 
+        .add_systems(Startup, (setup_camera, add_particles, audio::setup_audio_capture))
+        .add_systems(PostStartup, trail::add_particle_effects)
+        .add_systems(Update, (move_particles, keyboard_input, ui::control_panel, ensure_particle_count)) // check_field,
+        .add_systems(Update, (audio::update_band_energies, audio::apply_noise_modulation).chain().before(move_particles))
+        .add_systems(Update, apply_pending_trail_changes
+            .after(keyboard_input)
+            .after(ui::control_panel)
+            .after(audio::update_band_energies))
+        .add_systems(Update, trail::draw_trails.after(move_particles))
         .add_systems(Update, window_resize)
         .run();
 }
 
+/// Selects the active [`trail::TrailRenderer`] backend's Bevy plugin at compile time.
+struct TrailRendererPlugin;
+
+#[cfg(feature = "hanabi-trails")]
+impl Plugin for TrailRendererPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(HanabiPlugin);
+    }
+}
+
+#[cfg(not(feature = "hanabi-trails"))]
+impl Plugin for TrailRendererPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<FadeMaterial>::default());
+    }
+}
+
+/// Set by `keyboard_input`, `ui::control_panel` or `audio::update_band_energies` when the
+/// color scheme changes; consumed by
+/// `apply_pending_trail_changes`, which has `&mut World` access to drive whichever
+/// [`trail::TrailRenderer`] backend is active.
+#[derive(Resource, Default)]
+pub(crate) struct EffectsDirty(pub(crate) bool);
+
+fn apply_pending_trail_changes(world: &mut World) {
+    if world.resource::<EffectsDirty>().0 {
+        trail::change_particle_effects(world);
+        world.resource_mut::<EffectsDirty>().0 = false;
+    }
+}
+
 #[derive(Resource)]
 struct Luminosity(f32);
 
 #[derive(Resource)]
-struct ParticleCount(usize);
+pub(crate) struct ParticleCount(pub(crate) usize);
+
+/// Replaces the old `SPEED_FACTOR` const so the particle speed multiplier is tunable at runtime.
+#[derive(Resource)]
+pub(crate) struct SpeedFactor(pub(crate) f32);
 
 #[derive(Resource, Copy, Clone)]
-struct ColorScheme {
-    background: Color,
-    particle: Color,
-    luminosity: f32,
+pub struct ColorScheme {
+    pub background: Color,
+    pub particle: Color,
+    pub luminosity: f32,
 }
 
 impl ColorScheme {
@@ -96,7 +163,7 @@ impl ColorScheme {
 }
 
 #[derive(Component, Copy, Clone)]
-struct Particle {
+pub struct Particle {
     x: f32,
     y: f32,
 }
@@ -114,23 +181,6 @@ impl Particle {
 
 }
 
-#[derive(Resource)]
-struct NoiseGen {
-    gen: Perlin<2>,
-    noise_scale: f32,
-    seed: u64,
-}
-
-impl NoiseGen {
-    fn gen(&self, x: f32, y: f32, width: f32, height: f32) -> f64 {
-        // correct for origin being in the center, we want to have bottom left be the origin
-        let x = x + (width as f32 / 2.);
-        let y = y + (height as f32 / 2.);
-        self.gen.sample([x as f64 * self.noise_scale as f64, y as f64 * self.noise_scale as f64])
-    }
-}
-
-
 fn setup_camera(mut commands: Commands) {
     commands.spawn((Camera2dBundle {
         camera: Camera {
@@ -150,27 +200,30 @@ struct WindowSize {
 
 fn window_resize(mut resize_reader: EventReader<WindowResized>, 
                  mut window_size: ResMut<WindowSize>) {
-    for e in resize_reader.iter() {
+    for e in resize_reader.read() {
         window_size.width = e.width;
         window_size.height = e.height;
     }
 }
 
-fn add_particles(mut commands: Commands, window_size: Res<WindowSize>, particle_count: Res<ParticleCount>) {
+fn add_particles(mut commands: Commands, window_size: Res<WindowSize>, particle_count: Res<ParticleCount>, kind_weights: Res<ParticleKindWeights>) {
     for _ in 0..particle_count.0 as usize {
             // add a particle
             let particle: Particle = Particle::random(window_size.width, window_size.height);
 
-            commands.spawn(particle);
+            // SpatialBundle gives the particle a Transform/GlobalTransform so it's a valid
+            // hierarchy parent for the feedback backend's child trail quad; neither renderer
+            // reads the particle entity's own transform, both read `Particle.x/y` directly.
+            commands.spawn((particle, kind_weights.sample(), SpatialBundle::default()));
     }
 }
 
-fn ensure_particle_count(mut commands: Commands, particle_count: Res<ParticleCount>, mut particles: Query<Entity, With<Particle>>) {
+fn ensure_particle_count(mut commands: Commands, particle_count: Res<ParticleCount>, kind_weights: Res<ParticleKindWeights>, particles: Query<Entity, With<Particle>>) {
     if particles.iter().count() < particle_count.0 {
         // add a particle
         let particle: Particle = Particle::random(WIDTH as f32, HEIGHT as f32);
 
-        commands.spawn(particle);
+        commands.spawn((particle, kind_weights.sample(), SpatialBundle::default()));
     } else if particles.iter().count() > particle_count.0 {
         // remove a particle
         for entity in particles.iter().take(particles.iter().count() - particle_count.0) {
@@ -179,8 +232,14 @@ fn ensure_particle_count(mut commands: Commands, particle_count: Res<ParticleCou
     }
 }
 
-fn move_particles(mut particles: Query<(&mut Particle, &mut Transform)>, window_size: Res<WindowSize>, noise_gen: Res<NoiseGen>) {
-    for (mut particle, mut transform) in particles.iter_mut() {
+fn move_particles(mut particles: Query<(&mut Particle, &ParticleKind)>, window_size: Res<WindowSize>, noise_gen: Res<NoiseGen>, speed_factor: Res<SpeedFactor>, audio_reactivity: Res<AudioReactivity>, bands: Res<BandEnergies>) {
+    let speed = if audio_reactivity.enabled {
+        speed_factor.0 * (1. + bands.low * audio_reactivity.gain)
+    } else {
+        speed_factor.0
+    };
+
+    for (mut particle, kind) in particles.iter_mut() {
         // check if the particle is out of bounds
         if (particle.x >= (window_size.width / 2f32) as f32 || particle.y >= (window_size.height / 2f32) as f32 || particle.x <= -(window_size.width / 2f32) as f32 || particle.y <= -(window_size.height / 2f32) as f32) {
             // move the particle to a random position on the screen
@@ -188,37 +247,47 @@ fn move_particles(mut particles: Query<(&mut Particle, &mut Transform)>, window_
             particle.y = -window_size.height / 2. + rand::random::<f32>() * window_size.height;
         }
 
-        let sample = noise_gen.gen(particle.x, particle.y, window_size.width, window_size.height) * 2 as f64 * std::f64::consts::PI;
-        
-        particle.x += (sample.cos()) as f32 * SPEED_FACTOR;
-        particle.y += (sample.sin()) as f32 * SPEED_FACTOR;
+        let direction = noise_gen.sample_direction(particle.x, particle.y, window_size.width, window_size.height);
+        let profile = kind.profile();
 
-        transform.translation.x = particle.x;
-        transform.translation.y = particle.y;
+        particle.x += direction.x * speed * profile.speed_multiplier;
+        particle.y += direction.y * speed * profile.speed_multiplier - profile.gravity;
     }
 }
 
-fn keyboard_input(mut commands: Commands, 
-                  keys: Res<Input<KeyCode>>, 
+fn keyboard_input(keys: Res<Input<KeyCode>>,
                   window_size: Res<WindowSize>,
-                  mut noise_gen: ResMut<NoiseGen>, 
+                  mut noise_gen: ResMut<NoiseGen>,
                   mut color_scheme: ResMut<ColorScheme>,
-                  mut effects: ResMut<Assets<EffectAsset>>,
-                  mut particles: Query<&mut Particle>,
-                  mut particle_entities: Query<Entity, With<Particle>>) {
+                  mut effects_dirty: ResMut<EffectsDirty>,
+                  mut audio_reactivity: ResMut<audio::AudioReactivity>,
+                  mut particles: Query<&mut Particle>) {
     
     let mut should_change_particle_effects: bool = false;
     let mut new_color_scheme: ColorScheme = color_scheme.clone();
     
     if keys.just_pressed(KeyCode::Space) {
-        noise_gen.seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u64;
-        noise_gen.gen = Source::perlin(noise_gen.seed);
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u64;
+        noise_gen.reseed(seed);
+    }
+
+    if keys.just_pressed(KeyCode::M) {
+        // cycle to the next field-generation mode (Perlin -> Fbm -> Curl -> ...)
+        noise_gen.mode = noise_gen.mode.next();
     }
 
     if keys.just_pressed(KeyCode::Up) {
-        noise_gen.noise_scale /= 2.;
+        noise_gen.base_noise_scale /= 2.;
     } else if keys.just_pressed(KeyCode::Down) {
-        noise_gen.noise_scale *= 2.;
+        noise_gen.base_noise_scale *= 2.;
+    }
+
+    if keys.just_pressed(KeyCode::V) {
+        // toggle audio reactivity; capture the current luminosity as the baseline it modulates
+        audio_reactivity.enabled = !audio_reactivity.enabled;
+        if audio_reactivity.enabled {
+            audio_reactivity.base_luminosity = color_scheme.luminosity;
+        }
     }
 
     if keys.just_pressed(KeyCode::R) {
@@ -250,7 +319,7 @@ fn keyboard_input(mut commands: Commands,
         new_color_scheme.luminosity -= 1.;
         println!("Luminosity: {}", new_color_scheme.luminosity);
 
-    } else if (keys.just_pressed(KeyCode::D)) {
+    } else if keys.just_pressed(KeyCode::D) {
         // increase the luminosity
         should_change_particle_effects = true;
         new_color_scheme.luminosity += 1.;
@@ -258,80 +327,10 @@ fn keyboard_input(mut commands: Commands,
     }
 
     if should_change_particle_effects {
-        // commands.insert_resource(ClearColor(new_color_scheme.background.clone()));
-        change_particle_effects(commands, effects, particle_entities, new_color_scheme);
         color_scheme.particle = new_color_scheme.particle;
         color_scheme.background = new_color_scheme.background;
         color_scheme.luminosity = new_color_scheme.luminosity;
+        effects_dirty.0 = true;
     }
 
 }
-
-fn change_particle_effects(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>, particles: Query<Entity, With<Particle>>, color_scheme: ColorScheme) {
-    for entity in particles.iter() {
-        commands.entity(entity).remove::<ParticleEffectBundle>();
-    }
-
-    add_particle_effects(commands, effects, particles, color_scheme);
-}
-
-fn add_particle_effects(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>, particles: Query<Entity, With<Particle>>, color_scheme: ColorScheme) {
-    commands.insert_resource(ClearColor(color_scheme.background));
-    
-    for entity in particles.iter() {
-
-        let writer = ExprWriter::new();
-        let age = writer.lit(0.0).expr();
-        let init_age = SetAttributeModifier::new(Attribute::AGE, age);
-
-        let lifetime = writer.lit(1.).expr();
-        let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
-
-        let particle_color: Color = color_scheme.particle;
-
-        let luminosity: f32 = color_scheme.luminosity;
-        
-        let mut gradient = Gradient::new();
-        gradient.add_key(0.0, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, 0.2));
-        gradient.add_key(0.5, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, 0.05));
-        gradient.add_key(1.0, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, 0.0));
-
-        let init_pos = 
-            SetPositionCircleModifier {
-                center: writer.lit(Vec3::new(0., 0., 0.)).expr(),
-                radius: writer.lit(0.001).expr(),
-                axis: writer.lit(Vec3::Z).expr(),
-                dimension: ShapeDimension::Surface
-            };
-
-        let init_vel = 
-            SetVelocityCircleModifier {
-                center: writer.lit(Vec3::new(0.0, 0., 0.)).expr(),
-                axis: writer.lit(Vec3::Z).expr(),
-                speed: writer.lit(0.0001).expr()
-            };
-        
-        let effect = effects.add(
-            EffectAsset::new(1024, Spawner::rate(45.0.into()), writer.finish())
-            .with_name("trail")
-            .init(init_pos)
-            .init(init_vel)
-            .init(init_age)
-            .init(init_lifetime)
-            .render(SizeOverLifetimeModifier {
-                gradient: Gradient::constant(Vec2::splat(PARTICLE_RADIUS)),
-                screen_space_size: false,
-            })
-            .render(ColorOverLifetimeModifier { gradient })
-        );
-    
-        commands.entity(entity).insert(ParticleEffectBundle {
-            effect: ParticleEffect::new(effect).with_z_layer_2d(Some(0.)),
-            ..default()
-        });
-    }
-}
-
-fn draw_trails(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>, particles: Query<Entity, With<Particle>>, color_scheme: Res<ColorScheme>) {
-    add_particle_effects(commands, effects, particles, color_scheme.clone());
-}
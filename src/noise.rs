@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use libnoise::prelude::*;
+
+/// Selectable flow-field generation strategies, cycled at runtime via [`FieldMode::next`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FieldMode {
+    /// A single raw Perlin sample mapped to an angle (the original behavior).
+    Perlin,
+    /// Fractal Brownian motion: a weighted sum of Perlin octaves for multi-scale detail.
+    Fbm,
+    /// Curl noise: a divergence-free velocity derived from the curl of a scalar potential.
+    Curl,
+}
+
+impl FieldMode {
+    pub fn next(self) -> Self {
+        match self {
+            FieldMode::Perlin => FieldMode::Fbm,
+            FieldMode::Fbm => FieldMode::Curl,
+            FieldMode::Curl => FieldMode::Perlin,
+        }
+    }
+}
+
+/// Drives particle motion by sampling a pluggable noise field.
+#[derive(Resource)]
+pub struct NoiseGen {
+    pub source: Perlin<2>,
+    pub seed: u64,
+    /// The effective scale used by [`NoiseGen::sample_direction`]; on top of
+    /// [`NoiseGen::base_noise_scale`], this is what audio reactivity modulates each frame.
+    pub noise_scale: f32,
+    /// The user-set scale (via keyboard/egui); `noise_scale` tracks this directly when
+    /// audio reactivity is disabled.
+    pub base_noise_scale: f32,
+    pub mode: FieldMode,
+    /// Octave count used by [`FieldMode::Fbm`].
+    pub octaves: u32,
+    /// Per-octave frequency multiplier used by [`FieldMode::Fbm`].
+    pub lacunarity: f64,
+    /// Per-octave amplitude multiplier used by [`FieldMode::Fbm`].
+    pub persistence: f64,
+    /// Finite-difference step used by [`FieldMode::Curl`].
+    pub eps: f64,
+}
+
+impl NoiseGen {
+    pub fn new(seed: u64, noise_scale: f32) -> Self {
+        NoiseGen {
+            source: Source::perlin(seed),
+            seed,
+            noise_scale,
+            base_noise_scale: noise_scale,
+            mode: FieldMode::Perlin,
+            octaves: 4,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            eps: 0.5,
+        }
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.source = Source::perlin(seed);
+    }
+
+    // correct for origin being in the center, we want to have bottom left be the origin
+    fn to_field_space(&self, x: f32, y: f32, width: f32, height: f32) -> (f64, f64) {
+        let x = x + (width / 2.);
+        let y = y + (height / 2.);
+        (
+            x as f64 * self.noise_scale as f64,
+            y as f64 * self.noise_scale as f64,
+        )
+    }
+
+    fn sample_raw(&self, x: f64, y: f64) -> f64 {
+        self.source.sample([x, y])
+    }
+
+    fn sample_fbm(&self, x: f64, y: f64) -> f64 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut sum = 0.0;
+        let mut amplitude_sum = 0.0;
+
+        for _ in 0..self.octaves {
+            sum += amplitude * self.sample_raw(x * frequency, y * frequency);
+            amplitude_sum += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+
+        sum / amplitude_sum
+    }
+
+    // curl of the scalar potential `n`: (dn/dy, -dn/dx), which keeps the resulting
+    // velocity field divergence-free so particles don't collapse into sinks
+    fn sample_curl(&self, x: f64, y: f64) -> (f64, f64) {
+        let eps = self.eps;
+        let dn_dy = (self.sample_raw(x, y + eps) - self.sample_raw(x, y - eps)) / (2.0 * eps);
+        let dn_dx = (self.sample_raw(x + eps, y) - self.sample_raw(x - eps, y)) / (2.0 * eps);
+        (dn_dy, -dn_dx)
+    }
+
+    /// Samples a unit-length flow direction for a particle at `(x, y)`; in curl mode the
+    /// result is zero where the curl vanishes.
+    pub fn sample_direction(&self, x: f32, y: f32, width: f32, height: f32) -> Vec2 {
+        let (x, y) = self.to_field_space(x, y, width, height);
+
+        match self.mode {
+            FieldMode::Perlin => {
+                let angle = self.sample_raw(x, y) * 2. * std::f64::consts::PI;
+                Vec2::new(angle.cos() as f32, angle.sin() as f32)
+            }
+            FieldMode::Fbm => {
+                let angle = self.sample_fbm(x, y) * 2. * std::f64::consts::PI;
+                Vec2::new(angle.cos() as f32, angle.sin() as f32)
+            }
+            FieldMode::Curl => {
+                let (vx, vy) = self.sample_curl(x, y);
+                Vec2::new(vx as f32, vy as f32).normalize_or_zero()
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+/// How a kind's trail quad should be composited: additive kinds glow and overlap brightly,
+/// alpha kinds occlude like a conventional translucent sprite.
+#[derive(Copy, Clone, Debug)]
+pub enum BlendMode {
+    Additive,
+    Alpha,
+}
+
+/// Visual and motion parameters for one [`ParticleKind`], looked up once per particle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParticleKindProfile {
+    pub lifetime: f32,
+    pub blend: BlendMode,
+    pub speed_multiplier: f32,
+    /// Subtracted from the particle's y-velocity each tick: negative drifts upward (buoyant), positive sinks.
+    pub gravity: f32,
+    pub size_start: f32,
+    pub size_end: f32,
+    /// Hue shift applied on top of the base particle color, e.g. embers skew warm.
+    pub hue_shift: f32,
+}
+
+/// Particle archetype, modeled on classic particle engines (spark/smoke/ember/rain), assigned
+/// probabilistically on spawn via [`ParticleKindWeights`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ParticleKind {
+    /// Short-lived, bright, additive, shrinks to nothing.
+    Spark,
+    /// Long-lived, alpha-blended, grows as it drifts and dissipates.
+    Smoke,
+    /// Drifts and fades to a warm hue, additive.
+    Ember,
+    /// Falls with the flow field rather than against it, alpha-blended streak.
+    Rain,
+}
+
+impl ParticleKind {
+    pub fn profile(self) -> ParticleKindProfile {
+        match self {
+            ParticleKind::Spark => ParticleKindProfile {
+                lifetime: 0.4,
+                blend: BlendMode::Additive,
+                speed_multiplier: 1.6,
+                gravity: 0.0,
+                size_start: 1.0,
+                size_end: 0.0,
+                hue_shift: 0.0,
+            },
+            ParticleKind::Smoke => ParticleKindProfile {
+                lifetime: 3.0,
+                blend: BlendMode::Alpha,
+                speed_multiplier: 0.5,
+                gravity: -0.05,
+                size_start: 0.5,
+                size_end: 2.5,
+                hue_shift: 0.0,
+            },
+            ParticleKind::Ember => ParticleKindProfile {
+                lifetime: 1.5,
+                blend: BlendMode::Additive,
+                speed_multiplier: 0.9,
+                gravity: -0.02,
+                size_start: 1.0,
+                size_end: 0.3,
+                hue_shift: 0.08,
+            },
+            ParticleKind::Rain => ParticleKindProfile {
+                lifetime: 1.0,
+                blend: BlendMode::Alpha,
+                speed_multiplier: 1.2,
+                gravity: 0.15,
+                size_start: 1.0,
+                size_end: 1.0,
+                hue_shift: 0.0,
+            },
+        }
+    }
+
+    pub const ALL: [ParticleKind; 4] = [
+        ParticleKind::Spark,
+        ParticleKind::Smoke,
+        ParticleKind::Ember,
+        ParticleKind::Rain,
+    ];
+}
+
+/// Spawn-weight configuration for [`ParticleKind`], tunable via the egui panel.
+#[derive(Resource, Copy, Clone)]
+pub struct ParticleKindWeights {
+    pub spark: f32,
+    pub smoke: f32,
+    pub ember: f32,
+    pub rain: f32,
+}
+
+impl Default for ParticleKindWeights {
+    fn default() -> Self {
+        ParticleKindWeights {
+            spark: 0.4,
+            smoke: 0.2,
+            ember: 0.25,
+            rain: 0.15,
+        }
+    }
+}
+
+impl ParticleKindWeights {
+    /// Draws a kind with probability proportional to its weight.
+    pub fn sample(&self) -> ParticleKind {
+        let total = self.spark + self.smoke + self.ember + self.rain;
+        if total <= 0.0 {
+            return ParticleKind::Spark;
+        }
+
+        let mut roll = rand::random::<f32>() * total;
+        for kind in ParticleKind::ALL {
+            let weight = self.weight_of(kind);
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+
+        ParticleKind::Spark
+    }
+
+    fn weight_of(&self, kind: ParticleKind) -> f32 {
+        match kind {
+            ParticleKind::Spark => self.spark,
+            ParticleKind::Smoke => self.smoke,
+            ParticleKind::Ember => self.ember,
+            ParticleKind::Rain => self.rain,
+        }
+    }
+}
+
+/// Applies a [`ParticleKindProfile::hue_shift`] on top of a base particle color.
+pub fn shift_hue(color: Color, shift: f32) -> Color {
+    if shift == 0.0 {
+        return color;
+    }
+
+    let Color::Hsla { hue, saturation, lightness, alpha } = color.as_hsla() else {
+        return color;
+    };
+
+    Color::Hsla {
+        hue: (hue + shift * 360.0).rem_euclid(360.0),
+        saturation,
+        lightness,
+        alpha,
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+#[cfg(feature = "hanabi-trails")]
+pub use crate::trail_hanabi::HanabiTrailRenderer;
+
+#[cfg(not(feature = "hanabi-trails"))]
+pub use crate::trail_feedback::FeedbackTextureRenderer;
+
+/// A backend that turns particle entities into the glowing trail visuals on screen.
+///
+/// `bevy_hanabi` cannot target `wasm32-unknown-unknown`, so the active backend is chosen at
+/// compile time via the `hanabi-trails` feature: native builds default to [`HanabiTrailRenderer`],
+/// WASM builds fall back to [`FeedbackTextureRenderer`]. Both backends are driven by the same
+/// keyboard color/luminosity controls in `keyboard_input`.
+pub trait TrailRenderer: Send + Sync {
+    /// Attach trail visuals to every existing particle entity.
+    fn add_particle_effects(&mut self, world: &mut World);
+    /// Rebuild trail visuals after the color scheme changes.
+    fn change_particle_effects(&mut self, world: &mut World);
+    /// Per-frame trail upkeep (e.g. fading the feedback texture, or re-bucketing the Hanabi
+    /// spawners to follow the particles).
+    fn draw_trails(&mut self, world: &mut World);
+}
+
+/// Holds whichever [`TrailRenderer`] backend is active for this build.
+#[derive(Resource)]
+pub struct ActiveTrailRenderer(pub Box<dyn TrailRenderer>);
+
+impl Default for ActiveTrailRenderer {
+    fn default() -> Self {
+        #[cfg(feature = "hanabi-trails")]
+        {
+            ActiveTrailRenderer(Box::new(HanabiTrailRenderer::default()))
+        }
+        #[cfg(not(feature = "hanabi-trails"))]
+        {
+            ActiveTrailRenderer(Box::new(FeedbackTextureRenderer::default()))
+        }
+    }
+}
+
+pub fn add_particle_effects(world: &mut World) {
+    world.resource_scope(|world, mut renderer: Mut<ActiveTrailRenderer>| {
+        renderer.0.add_particle_effects(world);
+    });
+}
+
+pub fn change_particle_effects(world: &mut World) {
+    world.resource_scope(|world, mut renderer: Mut<ActiveTrailRenderer>| {
+        renderer.0.change_particle_effects(world);
+    });
+}
+
+pub fn draw_trails(world: &mut World) {
+    world.resource_scope(|world, mut renderer: Mut<ActiveTrailRenderer>| {
+        renderer.0.draw_trails(world);
+    });
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat, TextureUsages,
+        },
+        camera::RenderTarget,
+        view::RenderLayers,
+    },
+    sprite::{Material2d, MaterialMesh2dBundle},
+};
+
+use crate::particle_kind::{shift_hue, BlendMode, ParticleKind, ParticleKindProfile};
+use crate::trail::TrailRenderer;
+use crate::{ColorScheme, Particle};
+
+const TRAIL_LAYER: RenderLayers = RenderLayers::layer(1);
+/// Fraction of the previous frame kept each tick; the rest fades toward the background color.
+const FADE_RETAIN: f32 = 0.96;
+const PARTICLE_QUAD_SIZE: f32 = 2.5;
+
+/// Samples the previous frame's accumulated trail texture and fades it by `fade`,
+/// standing in for Hanabi's per-particle lifetime fade on backends without compute shaders.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct FadeMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    pub previous_frame: Handle<Image>,
+    #[uniform(2)]
+    pub fade: f32,
+}
+
+impl Material2d for FadeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/feedback_fade.wgsl".into()
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+struct ParticleQuad(Entity);
+
+/// Ping-pong render-texture bookkeeping for the feedback trail backend.
+#[derive(Resource)]
+struct FeedbackState {
+    textures: [Handle<Image>; 2],
+    fade_materials: [Handle<FadeMaterial>; 2],
+    trail_camera: Entity,
+    fade_quad: Entity,
+    display_quad: Entity,
+    write_index: usize,
+}
+
+/// WASM-compatible trail backend: accumulates particle positions into a persistent
+/// full-screen texture that is faded toward the background color each frame (a
+/// feedback/ping-pong pass), with particles drawn as small quads on top, one
+/// mesh/material pair shared per [`ParticleKind`].
+///
+/// `ColorMaterial` has no additive blend state on this Bevy version, so `BlendMode::Additive`
+/// is approximated with a brighter, more opaque quad rather than a true additive composite.
+#[derive(Default)]
+pub struct FeedbackTextureRenderer {
+    kind_assets: HashMap<ParticleKind, (Handle<Mesh>, Handle<ColorMaterial>)>,
+}
+
+impl TrailRenderer for FeedbackTextureRenderer {
+    fn add_particle_effects(&mut self, world: &mut World) {
+        let color_scheme = *world.resource::<ColorScheme>();
+        world.insert_resource(ClearColor(color_scheme.background));
+
+        if !world.contains_resource::<FeedbackState>() {
+            setup_feedback_pass(world);
+        }
+
+        self.kind_assets.clear();
+        for kind in ParticleKind::ALL {
+            self.kind_assets
+                .insert(kind, build_kind_assets(world, color_scheme, kind.profile()));
+        }
+
+        let particles: Vec<(Entity, ParticleKind)> = world
+            .query::<(Entity, &ParticleKind)>()
+            .iter(world)
+            .map(|(entity, kind)| (entity, *kind))
+            .collect();
+
+        for (entity, kind) in particles {
+            self.spawn_quad_for(world, entity, kind);
+        }
+    }
+
+    fn change_particle_effects(&mut self, world: &mut World) {
+        let color_scheme = *world.resource::<ColorScheme>();
+        world.insert_resource(ClearColor(color_scheme.background));
+
+        self.kind_assets.clear();
+        for kind in ParticleKind::ALL {
+            self.kind_assets
+                .insert(kind, build_kind_assets(world, color_scheme, kind.profile()));
+        }
+
+        let quads: Vec<(Entity, ParticleKind)> = world
+            .query::<(&ParticleQuad, &ParticleKind)>()
+            .iter(world)
+            .map(|(quad, kind)| (quad.0, *kind))
+            .collect();
+
+        for (quad_entity, kind) in quads {
+            let (_, material) = self.kind_assets[&kind].clone();
+            world.entity_mut(quad_entity).insert(material);
+        }
+    }
+
+    fn draw_trails(&mut self, world: &mut World) {
+        // Particles spawned since the last add/change pass (e.g. by `ensure_particle_count`
+        // reacting to the egui slider) have no quad yet; give them one now, the same way
+        // `HanabiTrailRenderer::rebatch` tracks particle-count changes every frame. Quads for
+        // removed particles need no cleanup here: they're children of their particle, so
+        // `despawn_recursive` takes them with it.
+        self.spawn_missing_quads(world);
+
+        let positions: Vec<(Entity, Vec2)> = world
+            .query::<(Entity, &Particle)>()
+            .iter(world)
+            .map(|(e, p)| (e, Vec2::new(p.x, p.y)))
+            .collect();
+
+        for (entity, pos) in positions {
+            if let Some(ParticleQuad(quad_entity)) = world.get::<ParticleQuad>(entity).copied() {
+                if let Some(mut transform) = world.get_mut::<Transform>(quad_entity) {
+                    transform.translation.x = pos.x;
+                    transform.translation.y = pos.y;
+                }
+            }
+        }
+
+        let (read_index, trail_camera, display_quad, fade_quad, fade_material, write_texture) = {
+            let state = world.resource::<FeedbackState>();
+            let write_index = state.write_index;
+            let read_index = 1 - write_index;
+            (
+                read_index,
+                state.trail_camera,
+                state.display_quad,
+                state.fade_quad,
+                state.fade_materials[write_index].clone(),
+                state.textures[write_index].clone(),
+            )
+        };
+
+        // `fade_materials[write_index]` already points its `previous_frame` at
+        // `textures[read_index]` (set up once in `setup_feedback_pass`); the only thing that
+        // changes frame to frame is which of the two static materials the single `fade_quad`
+        // is wearing, so swap that instead of re-deriving both materials' fields each tick.
+        world.entity_mut(fade_quad).insert(fade_material);
+
+        if let Some(mut camera) = world.get_mut::<Camera>(trail_camera) {
+            camera.target = RenderTarget::Image(write_texture.clone());
+        }
+        if let Some(mut sprite) = world.get_mut::<Handle<Image>>(display_quad) {
+            *sprite = write_texture;
+        }
+
+        world.resource_mut::<FeedbackState>().write_index = read_index;
+    }
+}
+
+impl FeedbackTextureRenderer {
+    fn spawn_quad_for(&self, world: &mut World, entity: Entity, kind: ParticleKind) {
+        let Some((mesh, material)) = self.kind_assets.get(&kind).cloned() else {
+            return;
+        };
+
+        let quad_entity = world
+            .spawn((
+                MaterialMesh2dBundle {
+                    mesh: mesh.into(),
+                    material,
+                    ..default()
+                },
+                TRAIL_LAYER,
+            ))
+            .id();
+
+        // Spawn as a child so `ensure_particle_count`'s `despawn_recursive` on the particle
+        // also despawns its quad, instead of leaking it when the egui particle-count slider
+        // is lowered.
+        world
+            .entity_mut(entity)
+            .insert(ParticleQuad(quad_entity))
+            .add_child(quad_entity);
+    }
+
+    fn spawn_missing_quads(&self, world: &mut World) {
+        if self.kind_assets.is_empty() {
+            return;
+        }
+
+        let missing: Vec<(Entity, ParticleKind)> = world
+            .query_filtered::<(Entity, &ParticleKind), Without<ParticleQuad>>()
+            .iter(world)
+            .map(|(entity, kind)| (entity, *kind))
+            .collect();
+
+        for (entity, kind) in missing {
+            self.spawn_quad_for(world, entity, kind);
+        }
+    }
+}
+
+fn setup_feedback_pass(world: &mut World) {
+    let window_size = {
+        let window_size = world.resource::<crate::WindowSize>();
+        (window_size.width.max(1.) as u32, window_size.height.max(1.) as u32)
+    };
+
+    let make_texture = |width: u32, height: u32| -> Image {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        image
+    };
+
+    let textures = {
+        let mut images = world.resource_mut::<Assets<Image>>();
+        [
+            images.add(make_texture(window_size.0, window_size.1)),
+            images.add(make_texture(window_size.0, window_size.1)),
+        ]
+    };
+
+    let quad = {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        meshes.add(Mesh::from(shape::Quad::new(Vec2::new(
+            window_size.0 as f32,
+            window_size.1 as f32,
+        ))))
+    };
+
+    let fade_materials = {
+        let mut materials = world.resource_mut::<Assets<FadeMaterial>>();
+        [
+            materials.add(FadeMaterial {
+                previous_frame: textures[1].clone(),
+                fade: FADE_RETAIN,
+            }),
+            materials.add(FadeMaterial {
+                previous_frame: textures[0].clone(),
+                fade: FADE_RETAIN,
+            }),
+        ]
+    };
+
+    let trail_camera = world
+        .spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(textures[0].clone()),
+                    order: -1,
+                    ..default()
+                },
+                ..default()
+            },
+            TRAIL_LAYER,
+        ))
+        .id();
+
+    let fade_quad = world
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: quad.clone().into(),
+                material: fade_materials[0].clone(),
+                transform: Transform::from_xyz(0., 0., -1.),
+                ..default()
+            },
+            TRAIL_LAYER,
+        ))
+        .id();
+
+    let display_quad = world
+        .spawn(SpriteBundle {
+            texture: textures[0].clone(),
+            ..default()
+        })
+        .id();
+
+    world.insert_resource(FeedbackState {
+        textures,
+        fade_materials,
+        trail_camera,
+        fade_quad,
+        display_quad,
+        write_index: 0,
+    });
+}
+
+fn build_kind_assets(
+    world: &mut World,
+    color_scheme: ColorScheme,
+    profile: ParticleKindProfile,
+) -> (Handle<Mesh>, Handle<ColorMaterial>) {
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Mesh::from(shape::Quad::new(
+        Vec2::splat(PARTICLE_QUAD_SIZE * profile.size_start),
+    )));
+
+    let color = shift_hue(color_scheme.particle, profile.hue_shift);
+    let (brightness, alpha) = match profile.blend {
+        BlendMode::Additive => (color_scheme.luminosity, 1.0),
+        BlendMode::Alpha => (color_scheme.luminosity * 0.5, 0.6),
+    };
+    let material = world
+        .resource_mut::<Assets<ColorMaterial>>()
+        .add(ColorMaterial::from((color * brightness).with_a(alpha)));
+
+    (mesh, material)
+}
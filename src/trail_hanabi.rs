@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_hanabi::prelude::*;
+
+use crate::particle_kind::{shift_hue, BlendMode, ParticleKind, ParticleKindProfile};
+use crate::trail::TrailRenderer;
+use crate::{ColorScheme, Particle};
+
+const PARTICLE_RADIUS: f32 = 2.;
+/// Screen-space cell size used to bucket particles into shared spawners.
+const CELL_SIZE: f32 = 48.;
+
+type BucketKey = (ParticleKind, IVec2);
+
+/// Native trail backend. Rather than giving every particle its own GPU spawner (which doesn't
+/// scale past a few thousand), particles are bucketed each frame by `(kind, screen-space cell)`
+/// into a handful of shared spawners, each driven by one of four [`ParticleKind`] `EffectAsset`s.
+/// `change_particle_effects` mutates those four assets in place instead of despawning and
+/// rebuilding every particle's bundle.
+#[derive(Default)]
+pub struct HanabiTrailRenderer {
+    effects: HashMap<ParticleKind, Handle<EffectAsset>>,
+    spawners: HashMap<BucketKey, Entity>,
+}
+
+impl TrailRenderer for HanabiTrailRenderer {
+    fn add_particle_effects(&mut self, world: &mut World) {
+        let color_scheme = *world.resource::<ColorScheme>();
+        world.insert_resource(ClearColor(color_scheme.background));
+
+        self.effects.clear();
+        {
+            let mut effects = world.resource_mut::<Assets<EffectAsset>>();
+            for kind in ParticleKind::ALL {
+                let handle = effects.add(build_trail_effect_asset(color_scheme, kind.profile()));
+                self.effects.insert(kind, handle);
+            }
+        }
+
+        self.rebatch(world);
+    }
+
+    fn change_particle_effects(&mut self, world: &mut World) {
+        let color_scheme = *world.resource::<ColorScheme>();
+        world.insert_resource(ClearColor(color_scheme.background));
+
+        let mut effects = world.resource_mut::<Assets<EffectAsset>>();
+        for kind in ParticleKind::ALL {
+            if let Some(asset) = self.effects.get(&kind).and_then(|handle| effects.get_mut(handle)) {
+                *asset = build_trail_effect_asset(color_scheme, kind.profile());
+            }
+        }
+        // The existing spawner entities keep their handles; mutating the assets in place is
+        // enough to pick up the new color scheme, no despawn/respawn needed.
+    }
+
+    fn draw_trails(&mut self, world: &mut World) {
+        self.rebatch(world);
+    }
+}
+
+impl HanabiTrailRenderer {
+    /// Groups live particles into screen-space cells per kind, spawning/moving/despawning the
+    /// shared spawner entities to match. Spawner count tracks occupied cells, not particle count.
+    fn rebatch(&mut self, world: &mut World) {
+        let mut buckets: HashMap<BucketKey, (Vec2, u32)> = HashMap::new();
+
+        {
+            let mut query = world.query::<(&Particle, &ParticleKind)>();
+            for (particle, kind) in query.iter(world) {
+                let cell = IVec2::new(
+                    (particle.x / CELL_SIZE).floor() as i32,
+                    (particle.y / CELL_SIZE).floor() as i32,
+                );
+                let entry = buckets.entry((*kind, cell)).or_insert((Vec2::ZERO, 0));
+                entry.0 += Vec2::new(particle.x, particle.y);
+                entry.1 += 1;
+            }
+        }
+
+        let stale: Vec<BucketKey> = self
+            .spawners
+            .keys()
+            .filter(|key| !buckets.contains_key(*key))
+            .copied()
+            .collect();
+
+        for key in stale {
+            if let Some(entity) = self.spawners.remove(&key) {
+                world.despawn(entity);
+            }
+        }
+
+        for (key, (sum, count)) in buckets {
+            let centroid = sum / count as f32;
+            let Some(effect) = self.effects.get(&key.0).cloned() else {
+                continue;
+            };
+
+            let entity = *self.spawners.entry(key).or_insert_with(|| {
+                world
+                    .spawn(ParticleEffectBundle {
+                        effect: ParticleEffect::new(effect).with_z_layer_2d(Some(0.)),
+                        ..default()
+                    })
+                    .id()
+            });
+
+            if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                transform.translation.x = centroid.x;
+                transform.translation.y = centroid.y;
+            }
+        }
+    }
+}
+
+fn build_trail_effect_asset(color_scheme: ColorScheme, profile: ParticleKindProfile) -> EffectAsset {
+    let writer = ExprWriter::new();
+    let age = writer.lit(0.0).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+
+    let lifetime = writer.lit(profile.lifetime).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+    let particle_color = shift_hue(color_scheme.particle, profile.hue_shift);
+    let luminosity: f32 = color_scheme.luminosity;
+    // The pinned bevy_hanabi version has no per-effect alpha-mode API (no `AlphaMode::Add`,
+    // no `EffectAsset::with_alpha_mode`), so additive-vs-alpha is approximated purely through
+    // the alpha gradient below: additive kinds start at a lower peak alpha than alpha kinds;
+    // both fall off with the same curve (a quarter of the peak at mid-life, zero at death).
+    let peak_alpha = match profile.blend {
+        BlendMode::Additive => 0.2,
+        BlendMode::Alpha => 0.6,
+    };
+
+    let mut gradient = Gradient::new();
+    gradient.add_key(0.0, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, peak_alpha));
+    gradient.add_key(0.5, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, peak_alpha * 0.25));
+    gradient.add_key(1.0, Vec4::new(particle_color.r() * luminosity, particle_color.g() * luminosity, particle_color.b() * luminosity, 0.0));
+
+    // Particles within a bucket spread out from the spawner's centroid rather than all
+    // stacking on one point, since one spawner now stands in for many real particles.
+    let init_pos = SetPositionCircleModifier {
+        center: writer.lit(Vec3::new(0., 0., 0.)).expr(),
+        radius: writer.lit(CELL_SIZE * 0.5).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        dimension: ShapeDimension::Surface,
+    };
+
+    let init_vel = SetVelocityCircleModifier {
+        center: writer.lit(Vec3::new(0.0, 0., 0.)).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        speed: writer.lit(0.0001).expr(),
+    };
+
+    let mut size_gradient = Gradient::new();
+    size_gradient.add_key(0.0, Vec2::splat(PARTICLE_RADIUS * profile.size_start));
+    size_gradient.add_key(1.0, Vec2::splat(PARTICLE_RADIUS * profile.size_end));
+
+    EffectAsset::new(1024, Spawner::rate(45.0.into()), writer.finish())
+        .with_name("trail")
+        .init(init_pos)
+        .init(init_vel)
+        .init(init_age)
+        .init(init_lifetime)
+        .render(SizeOverLifetimeModifier {
+            gradient: size_gradient,
+            screen_space_size: false,
+        })
+        .render(ColorOverLifetimeModifier { gradient })
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::audio::{AudioReactivity, BandEnergies};
+use crate::noise::FieldMode;
+use crate::particle_kind::ParticleKindWeights;
+use crate::{ColorScheme, EffectsDirty, NoiseGen, ParticleCount, SpeedFactor};
+
+/// Live side panel exposing every tunable resource as sliders/color pickers, alongside
+/// the keyboard shortcuts. Color/luminosity changes go through the same
+/// `EffectsDirty` path as `keyboard_input` so trails update immediately either way.
+pub fn control_panel(
+    mut contexts: EguiContexts,
+    mut particle_count: ResMut<ParticleCount>,
+    mut noise_gen: ResMut<NoiseGen>,
+    mut color_scheme: ResMut<ColorScheme>,
+    mut speed_factor: ResMut<SpeedFactor>,
+    mut effects_dirty: ResMut<EffectsDirty>,
+    mut audio_reactivity: ResMut<AudioReactivity>,
+    bands: Res<BandEnergies>,
+    mut kind_weights: ResMut<ParticleKindWeights>,
+) {
+    egui::SidePanel::right("flow_field_controls").show(contexts.ctx_mut(), |ui| {
+        ui.heading("Flow Field");
+
+        ui.add(egui::Slider::new(&mut particle_count.0, 100..=20_000).text("Particles"));
+        ui.add(egui::Slider::new(&mut speed_factor.0, 0.0..=5.0).text("Speed"));
+
+        ui.separator();
+        ui.label("Noise");
+        ui.add(
+            egui::Slider::new(&mut noise_gen.base_noise_scale, 0.0001..=0.01)
+                .logarithmic(true)
+                .text("Scale"),
+        );
+        egui::ComboBox::from_label("Mode")
+            .selected_text(format!("{:?}", noise_gen.mode))
+            .show_ui(ui, |ui| {
+                for mode in [FieldMode::Perlin, FieldMode::Fbm, FieldMode::Curl] {
+                    ui.selectable_value(&mut noise_gen.mode, mode, format!("{mode:?}"));
+                }
+            });
+        if noise_gen.mode == FieldMode::Fbm {
+            ui.add(egui::Slider::new(&mut noise_gen.octaves, 1..=8).text("Octaves"));
+            ui.add(egui::Slider::new(&mut noise_gen.lacunarity, 1.0..=4.0).text("Lacunarity"));
+            ui.add(egui::Slider::new(&mut noise_gen.persistence, 0.1..=1.0).text("Persistence"));
+        }
+        if noise_gen.mode == FieldMode::Curl {
+            ui.add(egui::Slider::new(&mut noise_gen.eps, 0.01..=2.0).text("Epsilon"));
+        }
+        ui.label(format!("Seed: {}", noise_gen.seed));
+        if ui.button("Reseed").clicked() {
+            noise_gen.reseed(rand::random());
+        }
+
+        ui.separator();
+        ui.label("Color");
+
+        let mut background = [
+            color_scheme.background.r(),
+            color_scheme.background.g(),
+            color_scheme.background.b(),
+        ];
+        let mut particle = [
+            color_scheme.particle.r(),
+            color_scheme.particle.g(),
+            color_scheme.particle.b(),
+        ];
+        let mut luminosity = color_scheme.luminosity;
+
+        let mut changed = false;
+        changed |= ui
+            .horizontal(|ui| {
+                ui.label("Background");
+                ui.color_edit_button_rgb(&mut background)
+            })
+            .inner
+            .changed();
+        changed |= ui
+            .horizontal(|ui| {
+                ui.label("Particle");
+                ui.color_edit_button_rgb(&mut particle)
+            })
+            .inner
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut luminosity, 1.0..=50.0).text("Luminosity"))
+            .changed();
+
+        if changed {
+            color_scheme.background = Color::rgb(background[0], background[1], background[2]);
+            color_scheme.particle = Color::rgb(particle[0], particle[1], particle[2]);
+            color_scheme.luminosity = luminosity;
+            effects_dirty.0 = true;
+        }
+
+        ui.separator();
+        ui.label("Audio Reactivity");
+        let mut enabled = audio_reactivity.enabled;
+        if ui.checkbox(&mut enabled, "Enabled").changed() {
+            audio_reactivity.enabled = enabled;
+            if enabled {
+                audio_reactivity.base_luminosity = color_scheme.luminosity;
+            }
+        }
+        ui.add(egui::Slider::new(&mut audio_reactivity.gain, 0.0..=5.0).text("Gain"));
+        ui.label(format!(
+            "low {:.2}  mid {:.2}  high {:.2}  loudness {:.2}",
+            bands.low, bands.mid, bands.high, bands.loudness
+        ));
+
+        ui.separator();
+        ui.label("Particle Kind Weights");
+        ui.add(egui::Slider::new(&mut kind_weights.spark, 0.0..=1.0).text("Spark"));
+        ui.add(egui::Slider::new(&mut kind_weights.smoke, 0.0..=1.0).text("Smoke"));
+        ui.add(egui::Slider::new(&mut kind_weights.ember, 0.0..=1.0).text("Ember"));
+        ui.add(egui::Slider::new(&mut kind_weights.rain, 0.0..=1.0).text("Rain"));
+    });
+}